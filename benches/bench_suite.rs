@@ -0,0 +1,7 @@
+//! Runs the benchmarks of the `- benches` suites of `tests/bench_suite.rs` with criterion.
+
+test_suite_rs::include_suite_benches!(
+    "../tests/bench_suite.rs",
+    bench_suite,
+    bench_suite::counted,
+);
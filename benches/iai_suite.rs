@@ -0,0 +1,9 @@
+//! Runs the benchmarks of the `- benches: iai` suites of `tests/iai_suite.rs` with iai-callgrind,
+//! which requires valgrind and the `iai-callgrind-runner` binary.
+
+test_suite_rs::include_suite_benches!(
+    iai,
+    "../tests/iai_suite.rs",
+    iai_suite,
+    iai_suite::counted,
+);
@@ -0,0 +1,2756 @@
+//! Procedural front-end of the `test_suite!` macro.
+//!
+//! The suite is parsed with syn so that mistakes are reported on the offending tokens, then
+//! handed over to the declarative macros of test_suite_rs which generate the code. Only what
+//! they can't express, such as `describe` and `it` aliases or name prefixes, is rewritten.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::buffer::Cursor;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token::{Brace, Paren};
+use syn::{
+    braced, parenthesized, Attribute, Error, Expr, FnArg, Ident, Item, ItemFn, ItemMod, Lit,
+    LitInt, LitStr, Pat, Path, ReturnType, Token, Type,
+};
+
+/// Block kinds taking a parenthesized list of inputs before their fixtures.
+const KINDS_WITH_INPUTS: &[&str] = &["prop", "fuzz", "quick", "bench"];
+/// Block kinds taking their fixtures right after their name.
+const KINDS_WITHOUT_INPUTS: &[&str] = &["proof", "loom", "shuttle"];
+/// Keywords preceding a regular test block, which choose whether it holds the suite mutex, make
+/// it async, skip it under Miri or on CI, or keep it out of the slow test warnings.
+const TEST_LOCKS: &[&str] = &[
+    "serial",
+    "parallel",
+    "async",
+    "miri_skip",
+    "quiet",
+    "ignore_on_ci",
+];
+/// Block kind naming a file that must fail to compile, as in `compile_fail test name = "file";`.
+const COMPILE_FAIL: &str = "compile_fail";
+/// Block kind of a criterion benchmark, which takes its bencher before its fixtures, as in
+/// `bench name(b: &mut Bencher) (fixtures) { ... }`.
+const BENCH: &str = "bench";
+
+pub(crate) fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let (mods, input) = split_name_path(input);
+    let (name, tokens) = parse_input(input)?;
+    let suite = quote! {
+        ::test_suite_rs::__test_suite_header! {
+            [name: #name] [setup:] [teardown:] []
+            #tokens
+        }
+    };
+    Ok(mods.iter().rev().fold(suite, |suite, name| {
+        quote! {
+            mod #name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #suite
+            }
+        }
+    }))
+}
+
+/// Splits the leading segments off the name of a suite such as `- name: integration::billing`,
+/// which become the mods enclosing the suite, leaving the suite named by its last segment.
+fn split_name_path(input: TokenStream) -> (Vec<Ident>, TokenStream) {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let is_punct = |index: usize, expected: char| matches!(tokens.get(index), Some(TokenTree::Punct(punct)) if punct.as_char() == expected);
+    let is_ident = |index: usize| matches!(tokens.get(index), Some(TokenTree::Ident(_)));
+    let is_name_line = is_punct(0, '-')
+        && matches!(tokens.get(1), Some(TokenTree::Ident(key)) if key == "name")
+        && is_punct(2, ':')
+        && is_ident(3);
+    let mut mods = Vec::new();
+    let mut index = 3;
+    while is_name_line
+        && is_punct(index + 1, ':')
+        && is_punct(index + 2, ':')
+        && is_ident(index + 3)
+    {
+        if let TokenTree::Ident(name) = &tokens[index] {
+            mods.push(name.clone());
+        }
+        index += 3;
+    }
+    if mods.is_empty() {
+        return (Vec::new(), tokens.into_iter().collect());
+    }
+    let rest = tokens[..3].iter().chain(&tokens[index..]).cloned();
+    (mods, rest.collect())
+}
+
+pub(crate) fn expand_suite_tests(input: TokenStream) -> syn::Result<TokenStream> {
+    let (_, input) = split_name_path(input);
+    let (name, tokens) = parse_input(input)?;
+    Ok(quote! {
+        ::test_suite_rs::__test_suite_header! {
+            [suite_tests: #name] [setup:] [teardown:] []
+            #tokens
+        }
+    })
+}
+
+/// Parses and checks a suite, returning its name and the tokens to hand over to the declarative
+/// backend.
+fn parse_input(input: TokenStream) -> syn::Result<(Ident, TokenStream)> {
+    let input = rewrite_aliases(input)?;
+    let suite: Suite = syn::parse2(input)?;
+    suite.check(None, &[])?;
+    Ok((suite.name, suite.tokens))
+}
+
+/// Rewrites the `describe "..." { ... }` and `it "..."` aliases of a suite into `mod` and
+/// `test` blocks named after their descriptions. Test bodies are left untouched.
+fn rewrite_aliases(input: TokenStream) -> syn::Result<TokenStream> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        match (&tokens[index], tokens.get(index + 1), tokens.get(index + 2)) {
+            (
+                TokenTree::Ident(keyword),
+                Some(TokenTree::Literal(description)),
+                Some(TokenTree::Group(body)),
+            ) if keyword == "describe" && body.delimiter() == Delimiter::Brace => {
+                output.push(Ident::new("mod", keyword.span()).into());
+                output.push(description_to_ident(description)?.into());
+                output.push(rewrite_group(body)?.into());
+                index += 3;
+            }
+            (TokenTree::Ident(keyword), Some(TokenTree::Literal(description)), _)
+                if keyword == "it" =>
+            {
+                output.push(Ident::new("test", keyword.span()).into());
+                output.push(description_to_ident(description)?.into());
+                index += 2;
+            }
+            (TokenTree::Ident(keyword), Some(name), Some(TokenTree::Group(body)))
+                if body.delimiter() == Delimiter::Brace
+                    && (keyword == "mod" && matches!(name, TokenTree::Ident(_))
+                        || keyword == "test_suite"
+                            && matches!(name, TokenTree::Punct(bang) if bang.as_char() == '!')) =>
+            {
+                output.push(keyword.clone().into());
+                output.push(name.clone());
+                output.push(rewrite_group(body)?.into());
+                index += 3;
+            }
+            (token, _, _) => {
+                output.push(token.clone());
+                index += 1;
+            }
+        }
+    }
+    Ok(output.into_iter().collect())
+}
+
+fn rewrite_group(group: &Group) -> syn::Result<Group> {
+    let mut rewritten = Group::new(group.delimiter(), rewrite_aliases(group.stream())?);
+    rewritten.set_span(group.span());
+    Ok(rewritten)
+}
+
+/// Derives an identifier from a description, e.g. `rejects_duplicate_emails` from
+/// `"Rejects duplicate emails"`.
+fn description_to_ident(description: &Literal) -> syn::Result<Ident> {
+    let Lit::Str(description) = Lit::new(description.clone()) else {
+        return Err(Error::new(
+            description.span(),
+            "expected a string describing the block",
+        ));
+    };
+
+    let mut name = String::new();
+    for c in description.value().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_');
+    if name.is_empty() {
+        return Err(Error::new(
+            description.span(),
+            "the description must contain at least one ASCII letter or digit",
+        ));
+    }
+    let name = if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name.to_owned()
+    };
+
+    // Keywords get a trailing underscore, as `r#` doesn't apply to all of them
+    match syn::parse_str::<Ident>(&name) {
+        Ok(_) => Ok(Ident::new(&name, description.span())),
+        Err(_) => Ok(Ident::new(&format!("{}_", name), description.span())),
+    }
+}
+
+pub(crate) fn expand_module(mut module: ItemMod) -> syn::Result<TokenStream> {
+    let Some((_, items)) = &mut module.content else {
+        return Err(Error::new_spanned(
+            &module,
+            "`#[test_suite]` only applies to inline modules",
+        ));
+    };
+
+    let mut setup = None;
+    let mut teardown = None;
+    for item in items.iter_mut() {
+        if let Item::Fn(function) = item {
+            for (marker, slot) in [("setup", &mut setup), ("teardown", &mut teardown)] {
+                if let Some(attr) = take_attribute(&mut function.attrs, marker) {
+                    if slot.is_some() {
+                        return Err(Error::new_spanned(
+                            attr,
+                            format!("the {} of the suite is already set", marker),
+                        ));
+                    }
+                    *slot = Some(function.sig.clone());
+                }
+            }
+        }
+    }
+
+    let mut errors: Option<Error> = None;
+    for item in items.iter_mut() {
+        if let Item::Fn(function) = item {
+            if take_attribute(&mut function.attrs, "suite_test").is_some() {
+                match suite_test(function, setup.as_ref(), teardown.as_ref()) {
+                    Ok(test) => *item = Item::Verbatim(test),
+                    Err(error) => match &mut errors {
+                        Some(errors) => errors.combine(error),
+                        None => errors = Some(error),
+                    },
+                }
+            }
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(quote!(#module)),
+    }
+}
+
+/// Removes the marker attribute `#[name]` from a list of attributes, returning it if present.
+fn take_attribute(attrs: &mut Vec<Attribute>, name: &str) -> Option<Attribute> {
+    let index = attrs.iter().position(|attr| attr.path().is_ident(name))?;
+    Some(attrs.remove(index))
+}
+
+/// Whether a pattern binds a value with `mut`, at any depth.
+fn is_mutable(pat: &Pat) -> bool {
+    match pat {
+        Pat::Ident(binding) => binding.mutability.is_some(),
+        Pat::Tuple(tuple) => tuple.elems.iter().any(is_mutable),
+        Pat::Type(typed) => is_mutable(&typed.pat),
+        _ => false,
+    }
+}
+
+/// Rewrites a `#[suite_test]` function into a test running between the setup and the teardown.
+fn suite_test(
+    function: &ItemFn,
+    setup: Option<&syn::Signature>,
+    teardown: Option<&syn::Signature>,
+) -> syn::Result<TokenStream> {
+    let ItemFn {
+        attrs, sig, block, ..
+    } = function;
+    let name = &sig.ident;
+
+    let mut params = Vec::new();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Typed(param) => params.push(param),
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new_spanned(
+                    receiver,
+                    "a suite test can't take `self`",
+                ));
+            }
+        }
+    }
+
+    let bindings = match setup {
+        None if params.is_empty() => quote!(),
+        None => {
+            return Err(Error::new_spanned(
+                &sig.inputs,
+                format!(
+                    "test `{}` binds fixture values but its suite has no setup, mark a function with \
+                     `#[setup]` or remove the parameters of the test",
+                    name
+                ),
+            ));
+        }
+        Some(setup) => {
+            let setup_name = &setup.ident;
+            let returned = match &setup.output {
+                ReturnType::Default => None,
+                ReturnType::Type(_, ty) => Some(&**ty),
+            };
+            let provided = match returned {
+                None => 0,
+                Some(Type::Tuple(tuple)) => tuple.elems.len(),
+                Some(_) => 1,
+            };
+            if params.len() != provided && !params.is_empty() {
+                return Err(Error::new_spanned(
+                    &sig.inputs,
+                    format!(
+                        "test `{}` binds {} fixture values but setup `{}` provides {}",
+                        name,
+                        params.len(),
+                        setup_name,
+                        provided
+                    ),
+                ));
+            }
+
+            let pats = params.iter().map(|param| &param.pat);
+            let tys = params.iter().map(|param| &param.ty);
+            // A mutable binding may not be written by every test, the others keep the lint
+            let allow = if params.iter().any(|param| is_mutable(&param.pat)) {
+                quote!(#[allow(unused_variables, unused_mut)])
+            } else {
+                quote!(#[allow(unused_variables)])
+            };
+            match (params.len(), returned) {
+                (0, _) => quote!(#setup_name();),
+                (1, Some(ty)) if !matches!(ty, Type::Tuple(_)) => {
+                    quote!(#allow let #(#pats)*: #(#tys)* = #setup_name();)
+                }
+                _ => quote!(#allow let (#(#pats,)*): (#(#tys,)*) = #setup_name();),
+            }
+        }
+    };
+    let teardown = teardown.map(|teardown| {
+        let teardown_name = &teardown.ident;
+        quote!(#teardown_name();)
+    });
+
+    let output = &sig.output;
+    let (closure_output, end) = match output {
+        ReturnType::Default => (quote!(), quote!(;)),
+        ReturnType::Type(arrow, ty) => (quote!(#arrow #ty), quote!()),
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #[test]
+        fn #name() #output {
+            #bindings
+            let test_result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| #closure_output #block));
+            let teardown_result = ::std::panic::catch_unwind(move || { #teardown });
+            ::test_suite_rs::__private::finish_test(
+                concat!(module_path!(), "::", stringify!(#name)),
+                test_result,
+                teardown_result,
+            ) #end
+        }
+    })
+}
+
+struct Suite {
+    name: Ident,
+    setup: Option<Setup>,
+    /// Resources declared with `- shared`, which tests bind alongside their fixture values.
+    shared: Vec<Ident>,
+    /// The key of the `- setup_once` line and the fixtures it names, which tests bind by name
+    /// too.
+    setup_once: Option<(Ident, Vec<Ident>)>,
+    blocks: Vec<Block>,
+    /// Whether the mods of the suite are flattened into the names of their tests.
+    flatten: bool,
+    /// What follows the name of the suite, as handed over to the declarative backend: options
+    /// handled here are left out and test names are rewritten.
+    tokens: TokenStream,
+}
+
+struct Setup {
+    path: Path,
+    provided: usize,
+}
+
+enum Block {
+    Test {
+        name: Ident,
+        /// Name of the generated function.
+        function: Ident,
+        fixtures: Option<Fixtures>,
+        /// Function of the test it runs after, in the same mod.
+        after: Option<Ident>,
+    },
+    Mod(Vec<Block>),
+    Suite(Suite),
+}
+
+struct Fixtures {
+    span: Span,
+    bound: usize,
+    /// The bindings, such as `mut buffer`, which may bind a shared resource or a once fixture
+    /// rather than a fixture value. Only `test` and `scenario` blocks bind those.
+    names: Vec<String>,
+}
+
+/// Where blocks are parsed, which decides the names of the functions generated for the tests.
+#[derive(Clone, Default)]
+struct Scope<'a> {
+    prefix: Option<&'a Ident>,
+    /// Set by `- flatten_names`: mods are merged into their suite and their names prepended to
+    /// the names of their tests.
+    flatten: bool,
+    /// Flattened mods enclosing the blocks, outermost first.
+    mods: Vec<Ident>,
+    /// Attributes of the flattened mods, applied to each of their blocks.
+    attrs: TokenStream,
+}
+
+impl Parse for Suite {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parse_suite(input, &Scope::default())
+    }
+}
+
+/// Parses a suite nested in `parent`, whose prefix and `- flatten_names` flag it inherits. Its own
+/// prefix takes precedence.
+fn parse_suite(input: ParseStream, parent: &Scope) -> syn::Result<Suite> {
+    let fork = input.fork();
+    let named = fork.parse::<Token![-]>().is_ok()
+        && fork.call(Ident::parse_any).is_ok_and(|key| key == "name");
+    let name = if named {
+        input.parse::<Token![-]>()?;
+        input.call(Ident::parse_any)?;
+        input.parse::<Token![:]>()?;
+        input.parse()?
+    } else {
+        // The suite of a file holding a single one needs no name
+        Ident::new("tests", Span::call_site())
+    };
+
+    let mut setup = None;
+    let mut teardown = None;
+    let mut teardown_variants = Vec::new();
+    let mut shared = Vec::new();
+    let mut setup_once = None;
+    let mut prefix = None;
+    let mut flatten = parent.flatten;
+    let mut tokens = TokenStream::new();
+    while input.peek(Token![-]) {
+        let line = input.cursor();
+        input.parse::<Token![-]>()?;
+        let key = input.call(Ident::parse_any)?;
+        match key.to_string().as_str() {
+            "setup" => {
+                if setup.is_some() {
+                    return Err(Error::new(
+                        key.span(),
+                        "the setup of the suite is already set",
+                    ));
+                }
+                input.parse::<Token![:]>()?;
+                let path = if peek_ident(input, "builtin") && input.peek2(Ident) {
+                    input.call(Ident::parse_any)?;
+                    let fixture: Ident = input.parse()?;
+                    if fixture != "alloc_counter" {
+                        return Err(Error::new(
+                            fixture.span(),
+                            format!("unknown builtin fixture `{}`", fixture),
+                        ));
+                    }
+                    // The fixture brings its own teardown
+                    if teardown.is_some() || !teardown_variants.is_empty() {
+                        return Err(Error::new(
+                            key.span(),
+                            "the teardown of the suite is already set",
+                        ));
+                    }
+                    teardown = Some(Path::from(fixture.clone()));
+                    Path::from(fixture)
+                } else {
+                    parse_path(input)?
+                };
+                let mut provided = 0;
+                if input.peek(Paren) {
+                    let content;
+                    parenthesized!(content in input);
+                    provided = Punctuated::<Type, Token![,]>::parse_terminated(&content)?.len();
+                    if provided == 0 {
+                        return Err(content.error("expected the types returned by the setup"));
+                    }
+                }
+                setup = Some(Setup { path, provided });
+            }
+            "teardown" => {
+                // A teardown is either given once or as variants qualified by a cfg predicate
+                let qualifier = if input.peek(Paren) {
+                    let content;
+                    parenthesized!(content in input);
+                    if content.is_empty() {
+                        return Err(content.error(
+                            "expected a cfg predicate such as `unix`, or `other` for the fallback",
+                        ));
+                    }
+                    Some(content.parse::<TokenStream>()?.to_string())
+                } else {
+                    None
+                };
+                if teardown.is_some() || qualifier.is_none() && !teardown_variants.is_empty() {
+                    return Err(Error::new(
+                        key.span(),
+                        "the teardown of the suite is already set",
+                    ));
+                }
+                input.parse::<Token![:]>()?;
+                let path = parse_path(input)?;
+                match qualifier {
+                    Some(qualifier) if teardown_variants.contains(&qualifier) => {
+                        return Err(Error::new(
+                            key.span(),
+                            format!("the `{}` teardown of the suite is already set", qualifier),
+                        ));
+                    }
+                    Some(qualifier) => teardown_variants.push(qualifier),
+                    None => teardown = Some(path),
+                }
+            }
+            "shared" => {
+                input.parse::<Token![:]>()?;
+                let name: Ident = input.parse()?;
+                input.parse::<Token![:]>()?;
+                input.parse::<Type>()?;
+                input.parse::<Token![=]>()?;
+                // A full expression would take the `-` of the next line for a subtraction
+                if input.peek(Brace) {
+                    input.parse::<syn::Block>()?;
+                } else {
+                    parse_path(input)?;
+                    if !input.peek(Paren) {
+                        return Err(input.error(
+                            "expected a call such as `open_device()` or a block initializing the resource",
+                        ));
+                    }
+                    input.parse::<TokenTree>()?;
+                }
+                if shared.contains(&name) {
+                    return Err(Error::new(
+                        name.span(),
+                        format!("shared `{}` is already declared", name),
+                    ));
+                }
+                shared.push(name);
+            }
+            "setup_once" => {
+                if setup_once.is_some() {
+                    return Err(Error::new(
+                        key.span(),
+                        "the once setup of the suite is already set",
+                    ));
+                }
+                input.parse::<Token![:]>()?;
+                parse_path(input)?;
+                let content;
+                parenthesized!(content in input);
+                let mut fields = Vec::new();
+                while !content.is_empty() {
+                    fields.push(content.parse::<Ident>()?);
+                    content.parse::<Token![:]>()?;
+                    content.parse::<Type>()?;
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+                if fields.is_empty() {
+                    return Err(
+                        content.error("expected the fixtures set up once, such as `cache: Cache`")
+                    );
+                }
+                setup_once = Some((key.clone(), fields));
+            }
+            "migrate" => {
+                input.parse::<Token![:]>()?;
+                parse_path(input)?;
+            }
+            "proptest_cases" => {
+                input.parse::<Token![:]>()?;
+                input.parse::<LitInt>()?;
+            }
+            "artifacts" | "seeded_rng" | "target" | "miri" | "harness" | "runtime"
+            | "tokio_time" => {
+                input.parse::<Token![:]>()?;
+                input.parse::<Ident>()?;
+            }
+            "prefix" => {
+                if prefix.is_some() {
+                    return Err(Error::new(
+                        key.span(),
+                        "the prefix of the suite is already set",
+                    ));
+                }
+                input.parse::<Token![:]>()?;
+                prefix = Some(input.call(Ident::parse_any)?);
+                continue;
+            }
+            "flatten_names" => {
+                flatten = true;
+                continue;
+            }
+            "env" | "failpoints" => {
+                input.parse::<Token![:]>()?;
+                let content;
+                braced!(content in input);
+                while !content.is_empty() {
+                    content.parse::<LitStr>()?;
+                    content.parse::<Token![=>]>()?;
+                    content.parse::<Expr>()?;
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            }
+            "insta" => {
+                input.parse::<Token![:]>()?;
+                let content;
+                braced!(content in input);
+                while !content.is_empty() {
+                    content.parse::<Ident>()?;
+                    content.parse::<Token![:]>()?;
+                    content.parse::<Expr>()?;
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            }
+            "const" => parse_const(input)?,
+            "serial" => {
+                if input.parse::<Option<Token![:]>>()?.is_some() {
+                    input.parse::<Ident>()?;
+                }
+            }
+            "tracing" => {
+                input.parse::<Token![:]>()?;
+                let mode = input.call(Ident::parse_any)?;
+                if mode != "capture" {
+                    return Err(Error::new(
+                        mode.span(),
+                        format!("unknown tracing mode `{}`, expected `capture`", mode),
+                    ));
+                }
+            }
+            "audit" => {
+                input.parse::<Token![:]>()?;
+                let mode = input.call(Ident::parse_any)?;
+                if mode != "shuffle" {
+                    return Err(Error::new(
+                        mode.span(),
+                        format!("unknown audit mode `{}`, expected `shuffle`", mode),
+                    ));
+                }
+            }
+            "nextest" => {
+                input.parse::<Token![:]>()?;
+                let mode = input.call(Ident::parse_any)?;
+                if mode != "per_process_setup" {
+                    return Err(Error::new(
+                        mode.span(),
+                        format!(
+                            "unknown nextest mode `{}`, expected `per_process_setup`",
+                            mode
+                        ),
+                    ));
+                }
+            }
+            "warn_slow" => {
+                input.parse::<Token![:]>()?;
+                parse_duration(input)?;
+            }
+            "teardown_timeout" => {
+                input.parse::<Token![:]>()?;
+                parse_duration(input)?;
+            }
+            "setup_budget" => {
+                input.parse::<Token![:]>()?;
+                if !input.peek(LitInt) {
+                    let mode = input.call(Ident::parse_any)?;
+                    if mode != "warn" {
+                        return Err(Error::new(
+                            mode.span(),
+                            format!("unknown setup budget mode `{}`, expected `warn`", mode),
+                        ));
+                    }
+                }
+                parse_duration(input)?;
+            }
+            "report" => {
+                input.parse::<Token![:]>()?;
+                let format = input.call(Ident::parse_any)?;
+                if format != "junit" && format != "json" {
+                    return Err(Error::new(
+                        format.span(),
+                        format!(
+                            "unknown report format `{}`, expected `junit` or `json`",
+                            format
+                        ),
+                    ));
+                }
+                let content;
+                parenthesized!(content in input);
+                content.parse::<LitStr>()?;
+            }
+            "framework" => {
+                input.parse::<Token![:]>()?;
+                let framework = input.call(Ident::parse_any)?;
+                if framework != "custom" {
+                    return Err(Error::new(
+                        framework.span(),
+                        format!("unknown framework `{}`, expected `custom`", framework),
+                    ));
+                }
+                let content;
+                parenthesized!(content in input);
+                content.parse::<Path>()?;
+            }
+            "init_logger" => {
+                if input.parse::<Option<Token![:]>>()?.is_some() {
+                    if input.peek(LitStr) {
+                        input.parse::<LitStr>()?;
+                    } else {
+                        input.call(Ident::parse_any)?;
+                    }
+                }
+            }
+            "benches" => {
+                if input.parse::<Option<Token![:]>>()?.is_some() {
+                    let harness = input.call(Ident::parse_any)?;
+                    if harness != "iai" {
+                        return Err(Error::new(
+                            harness.span(),
+                            format!("unknown benchmark harness `{}`, expected `iai`", harness),
+                        ));
+                    }
+                }
+            }
+            "capture_setup" | "checkpoint_mocks" | "fuzz_targets" | "inherit_scope"
+            | "leak_check" | "log" | "no_std" | "ordered" | "summary" | "timings" => {}
+            "name" => {
+                return Err(Error::new(
+                    key.span(),
+                    "the name of the suite is already set",
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    key.span(),
+                    format!("unknown suite option `{}`", key),
+                ))
+            }
+        }
+        tokens.extend(tokens_between(line, input.cursor()));
+    }
+
+    let scope = Scope {
+        prefix: prefix.as_ref().or(parent.prefix),
+        flatten,
+        ..Scope::default()
+    };
+    let mut blocks = Vec::new();
+    parse_blocks(input, &scope, &mut blocks, &mut tokens)?;
+    Ok(Suite {
+        name,
+        setup,
+        shared,
+        setup_once,
+        blocks,
+        flatten,
+        tokens,
+    })
+}
+
+impl Suite {
+    /// Checks the fixtures bound by the tests against the setup of the suite, or the one
+    /// inherited from the enclosing suite, leaving the shared resources and once fixtures of the
+    /// outermost suite out. All the mismatches are reported at once.
+    fn check(&self, inherited: Option<&Setup>, scoped: &[String]) -> syn::Result<()> {
+        let mut errors = None;
+        let own = self.scoped();
+        let scoped = if own.is_empty() { scoped } else { &own };
+        check_blocks(
+            &self.blocks,
+            self.setup.as_ref().or(inherited),
+            scoped,
+            &mut errors,
+        );
+        check_dependencies(&self.blocks, &mut errors);
+        if self.flatten {
+            check_collisions(&self.blocks, &mut errors);
+        }
+        errors.map_or(Ok(()), Err)
+    }
+
+    /// The bindings of the shared resources and once fixtures of the suite, with and without
+    /// `mut`.
+    fn scoped(&self) -> Vec<String> {
+        let once = self.setup_once.iter().flat_map(|(_, fields)| fields);
+        self.shared
+            .iter()
+            .chain(once)
+            .flat_map(|name| [name.to_string(), format!("mut {}", name)])
+            .collect()
+    }
+}
+
+fn check_blocks(
+    blocks: &[Block],
+    setup: Option<&Setup>,
+    scoped: &[String],
+    errors: &mut Option<Error>,
+) {
+    for block in blocks {
+        let error = match block {
+            Block::Test {
+                name,
+                fixtures: Some(fixtures),
+                ..
+            } => match (setup, fixtures.bound - fixtures.scoped(scoped)) {
+                (_, 0) => None,
+                (None, _) => Some(Error::new(
+                    fixtures.span,
+                    format!(
+                        "test `{}` binds fixture values but its suite has no setup, add a `- setup:` line \
+                         to the suite or remove the argument list of the test",
+                        name
+                    ),
+                )),
+                (Some(setup), bound) if setup.provided != bound => Some(Error::new(
+                    fixtures.span,
+                    format!(
+                        "test `{}` binds {} fixture values but setup `{}` provides {}",
+                        name,
+                        bound,
+                        path_to_string(&setup.path),
+                        setup.provided
+                    ),
+                )),
+                (Some(_), _) => None,
+            },
+            Block::Test { fixtures: None, .. } => None,
+            Block::Mod(blocks) => {
+                check_blocks(blocks, setup, scoped, errors);
+                check_dependencies(blocks, errors);
+                None
+            }
+            Block::Suite(suite) => {
+                if let Some(name) = suite.shared.first() {
+                    push_error(
+                        errors,
+                        Error::new(
+                            name.span(),
+                            format!(
+                                "shared `{}` must be declared by the outermost suite, whose shared \
+                                 resources its nested suites bind",
+                                name
+                            ),
+                        ),
+                    );
+                }
+                if let Some((key, _)) = &suite.setup_once {
+                    push_error(
+                        errors,
+                        Error::new(
+                            key.span(),
+                            "`- setup_once` must be declared by the outermost suite, whose once \
+                             fixtures its nested suites bind",
+                        ),
+                    );
+                }
+                suite.check(setup, scoped).err()
+            }
+        };
+        if let Some(error) = error {
+            push_error(errors, error);
+        }
+    }
+}
+
+/// Checks that the tests declared `after` another one, among the blocks of a mod, run after a
+/// test of the same mod and don't depend on themselves. Each cycle is reported once.
+fn check_dependencies(blocks: &[Block], errors: &mut Option<Error>) {
+    let mut functions = HashSet::new();
+    let mut dependencies = HashMap::new();
+    for block in blocks {
+        if let Block::Test {
+            function, after, ..
+        } = block
+        {
+            functions.insert(function.to_string());
+            if let Some(after) = after {
+                dependencies.insert(function.to_string(), after.to_string());
+            }
+        }
+    }
+
+    let mut reported = HashSet::new();
+    for block in blocks {
+        let Block::Test {
+            function,
+            after: Some(after),
+            ..
+        } = block
+        else {
+            continue;
+        };
+        if !functions.contains(&after.to_string()) {
+            push_error(
+                errors,
+                Error::new(
+                    after.span(),
+                    format!(
+                        "test `{}` runs after `{}`, which isn't a test of its mod",
+                        function, after
+                    ),
+                ),
+            );
+            continue;
+        }
+        let mut chain = vec![function.to_string()];
+        let mut current = after.to_string();
+        while !chain.contains(&current) {
+            chain.push(current.clone());
+            match dependencies.get(&current) {
+                Some(next) => current = next.clone(),
+                None => break,
+            }
+        }
+        if current == chain[0] && reported.insert(current.clone()) {
+            reported.extend(chain.iter().cloned());
+            chain.push(current);
+            push_error(
+                errors,
+                Error::new(
+                    after.span(),
+                    format!(
+                        "test `{}` depends on itself through the tests it runs after: {}",
+                        function,
+                        chain.join(" -> ")
+                    ),
+                ),
+            );
+        }
+    }
+}
+
+/// Checks that flattening the mods of a suite didn't give the same name to two tests.
+fn check_collisions(blocks: &[Block], errors: &mut Option<Error>) {
+    let mut functions = HashSet::new();
+    for block in blocks {
+        if let Block::Test { function, .. } = block {
+            if !functions.insert(function.to_string()) {
+                push_error(
+                    errors,
+                    Error::new(
+                        function.span(),
+                        format!(
+                            "two tests are named `{}` once the mods of the suite are flattened",
+                            function
+                        ),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn push_error(errors: &mut Option<Error>, error: Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
+    }
+}
+
+/// Parses a setup or teardown path, which the declarative backend wants relative.
+fn parse_path(input: ParseStream) -> syn::Result<Path> {
+    let path = input.call(Path::parse_mod_style)?;
+    if let Some(colon) = path.leading_colon {
+        return Err(Error::new_spanned(
+            colon,
+            "start the path with `crate` instead of `::`",
+        ));
+    }
+    Ok(path)
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Parses the `NAME: Type = value` part of a `- const` line.
+fn parse_const(input: ParseStream) -> syn::Result<()> {
+    input.parse::<Ident>()?;
+    input.parse::<Token![:]>()?;
+    input.parse::<Type>()?;
+    input.parse::<Token![=]>()?;
+
+    if input.peek(Lit) {
+        input.parse::<Lit>()?;
+    } else if input.peek(Brace) {
+        let content;
+        braced!(content in input);
+        content.parse::<TokenStream>()?;
+    } else if input.peek(Ident) && input.peek2(Token![!]) {
+        let mac = input.parse::<Ident>()?;
+        input.parse::<Token![!]>()?;
+        let content;
+        parenthesized!(content in input);
+        content.parse::<LitStr>()?;
+        if mac == "option_env" {
+            input.parse::<Token![.]>()?;
+            let method = input.parse::<Ident>()?;
+            if method != "unwrap_or" {
+                return Err(Error::new(method.span(), "expected `unwrap_or`"));
+            }
+            let default;
+            parenthesized!(default in input);
+            default.parse::<Expr>()?;
+        } else if mac != "env" {
+            return Err(Error::new(mac.span(), "expected `env!` or `option_env!`"));
+        }
+    } else {
+        return Err(input.error(
+            "expected a literal, a block, `env!(\"VAR\")` or `option_env!(\"VAR\").unwrap_or(default)`",
+        ));
+    }
+    Ok(())
+}
+
+/// Parses the content of a suite or of one of its mods: test blocks, mods, nested suites and items.
+/// They are appended to `blocks`, and their tokens to `tokens` with the test names rewritten.
+fn parse_blocks(
+    input: ParseStream,
+    scope: &Scope,
+    blocks: &mut Vec<Block>,
+    tokens: &mut TokenStream,
+) -> syn::Result<()> {
+    while !input.is_empty() {
+        let begin = input.cursor();
+        input.call(Attribute::parse_outer)?;
+        let mut attrs = scope.attrs.clone();
+        attrs.extend(tokens_between(begin, input.cursor()));
+
+        if input.peek(Token![mod]) && input.peek2(Ident) && input.peek3(Brace) {
+            let keyword = input.parse::<Token![mod]>()?;
+            let name = input.parse::<Ident>()?;
+            let content;
+            let brace = braced!(content in input);
+            if scope.flatten {
+                let mut inner = scope.clone();
+                inner.mods.push(name);
+                inner.attrs = attrs;
+                parse_blocks(&content, &inner, blocks, tokens)?;
+            } else {
+                let mut mod_blocks = Vec::new();
+                let mut body = TokenStream::new();
+                parse_blocks(&content, scope, &mut mod_blocks, &mut body)?;
+                blocks.push(Block::Mod(mod_blocks));
+                tokens.extend(attrs);
+                tokens.extend(quote!(#keyword #name));
+                tokens.extend([braced_group(brace, body)]);
+            }
+            continue;
+        }
+
+        tokens.extend(attrs);
+        if peek_ident(input, "test_suite") && input.peek2(Token![!]) && input.peek3(Brace) {
+            let mac = input.parse::<Ident>()?;
+            let bang = input.parse::<Token![!]>()?;
+            let content;
+            let brace = braced!(content in input);
+            let suite = parse_suite(&content, scope)?;
+            let (name, body) = (&suite.name, &suite.tokens);
+            tokens.extend(quote!(#mac #bang));
+            tokens.extend([braced_group(brace, quote!(- name: #name #body))]);
+            blocks.push(Block::Suite(suite));
+        } else if let Some(test) = parse_test(input, scope, TokenStream::new(), tokens)? {
+            blocks.push(test);
+        } else {
+            let begin = input.cursor();
+            input.parse::<Item>()?;
+            tokens.extend(tokens_between(begin, input.cursor()));
+        }
+    }
+    Ok(())
+}
+
+fn braced_group(brace: Brace, stream: TokenStream) -> TokenTree {
+    let mut group = Group::new(Delimiter::Brace, stream);
+    group.set_span(brace.span.join());
+    group.into()
+}
+
+/// Collects the tokens parsed between two cursors of the same group.
+fn tokens_between(begin: Cursor, end: Cursor) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let mut cursor = begin;
+    while cursor < end {
+        let Some((token, next)) = cursor.token_tree() else {
+            break;
+        };
+        tokens.extend([token]);
+        cursor = next;
+    }
+    tokens
+}
+
+fn peek_ident(input: ParseStream, expected: &str) -> bool {
+    input
+        .cursor()
+        .ident()
+        .is_some_and(|(ident, _)| ident == expected)
+}
+
+/// Parses a test block of any kind, if the input starts with one, and appends its tokens to
+/// `tokens` with its name rewritten. The `focus`, `skip(...)` and `ignore_on(...)` markers
+/// preceding the block are accumulated in `prefix`, which follows the attributes added to the
+/// block.
+fn parse_test(
+    input: ParseStream,
+    scope: &Scope,
+    mut prefix: TokenStream,
+    tokens: &mut TokenStream,
+) -> syn::Result<Option<Block>> {
+    let Some((first, next)) = input.cursor().ident() else {
+        return Ok(None);
+    };
+    if first == "focus" && next.ident().is_some() {
+        input.parse::<Ident>()?;
+        prefix.extend(quote!(#first));
+        return match parse_test(input, scope, prefix, tokens)? {
+            Some(test) => Ok(Some(test)),
+            None => Err(Error::new(
+                first.span(),
+                "expected a test after `focus`, such as `focus test reproduces_bug {}`",
+            )),
+        };
+    }
+    let followed_by_test = next.ident().is_some_and(|(ident, _)| ident == "test");
+    if first == "skip" && (followed_by_test || next.group(Delimiter::Parenthesis).is_some()) {
+        let begin = input.cursor();
+        input.parse::<Ident>()?;
+        let has_reason = !followed_by_test && {
+            let content;
+            parenthesized!(content in input);
+            content.parse::<LitStr>().is_ok() && content.is_empty()
+        };
+        if !has_reason || !peek_ident(input, "test") {
+            return Err(Error::new(
+                first.span(),
+                "expected the reason the test is skipped, such as `skip(\"waiting on fixture rework\") test`",
+            ));
+        }
+        prefix.extend(tokens_between(begin, input.cursor()));
+        return parse_test(input, scope, prefix, tokens);
+    }
+    if first == "ignore_on" && next.group(Delimiter::Parenthesis).is_some() {
+        let begin = input.cursor();
+        input.parse::<Ident>()?;
+        let content;
+        parenthesized!(content in input);
+        let keyword = content.parse::<Ident>()?;
+        if keyword != "env" {
+            return Err(Error::new(
+                keyword.span(),
+                "expected `env`, such as `ignore_on(env = \"HEADLESS\") test`",
+            ));
+        }
+        content.parse::<Token![=]>()?;
+        content.parse::<LitStr>()?;
+        if !content.is_empty() || !peek_ident(input, "test") {
+            return Err(Error::new(
+                first.span(),
+                "expected a test ignored on a single variable, such as `ignore_on(env = \"HEADLESS\") test`",
+            ));
+        }
+        prefix.extend(tokens_between(begin, input.cursor()));
+        return parse_test(input, scope, prefix, tokens);
+    }
+    let is_locked = TEST_LOCKS.contains(&first.to_string().as_str()) && followed_by_test;
+    let kind = if is_locked {
+        "test".to_string()
+    } else {
+        first.to_string()
+    };
+    let is_regular =
+        is_locked || ((kind == "test" || kind == "scenario") && next.ident().is_some());
+    let is_other = (KINDS_WITH_INPUTS.contains(&kind.as_str())
+        || KINDS_WITHOUT_INPUTS.contains(&kind.as_str())
+        || kind == COMPILE_FAIL)
+        && followed_by_test;
+    let is_bench = kind == BENCH && !followed_by_test && next.ident().is_some();
+    if !is_regular && !is_other && !is_bench {
+        return Ok(None);
+    }
+
+    if !scope.mods.is_empty() {
+        // The double underscores joining the mods and the test aren't snake case
+        tokens.extend(quote!(#[allow(non_snake_case)]));
+    }
+    tokens.extend(prefix);
+    let begin = input.cursor();
+    input.call(Ident::parse_any)?;
+    if is_other || is_locked {
+        input.parse::<Ident>()?;
+    }
+    tokens.extend(tokens_between(begin, input.cursor()));
+    let name: Ident = input.parse()?;
+    let function = function_name(scope, &name)?;
+    tokens.extend([TokenTree::from(function.clone())]);
+
+    // The dependency is renamed like the test, being in the same mod
+    let after = if kind == "test" && peek_ident(input, "after") {
+        let keyword = input.parse::<Ident>()?;
+        let after = function_name(scope, &input.parse()?)?;
+        tokens.extend(quote!(#keyword #after));
+        Some(after)
+    } else {
+        None
+    };
+    let after_name = input.cursor();
+
+    let fixtures = if kind == COMPILE_FAIL {
+        input.parse::<Token![=]>()?;
+        input.parse::<LitStr>()?;
+        input.parse::<Token![;]>()?;
+        tokens.extend(tokens_between(after_name, input.cursor()));
+        return Ok(Some(Block::Test {
+            name,
+            function,
+            fixtures: None,
+            after: None,
+        }));
+    } else if is_regular {
+        if first == "async" && peek_ident(input, "realtime") {
+            input.parse::<Ident>()?;
+        }
+        if kind == "test" && peek_ident(input, "within") {
+            input.parse::<Ident>()?;
+            parse_duration(input)?;
+        }
+        if kind == "test" && peek_ident(input, "stack") {
+            input.parse::<Ident>()?;
+            let size = input.parse::<LitInt>()?;
+            if !matches!(size.suffix(), "KB" | "MB" | "GB") {
+                return Err(Error::new(
+                    size.span(),
+                    "expected a stack size with a unit among `KB`, `MB` and `GB`, e.g. `16MB`",
+                ));
+            }
+        }
+        if kind == "test" && peek_ident(input, "tags") && input.peek2(Paren) {
+            input.parse::<Ident>()?;
+            let content;
+            parenthesized!(content in input);
+            if Punctuated::<Ident, Token![,]>::parse_terminated(&content)?.is_empty() {
+                return Err(
+                    content.error("expected the tags of the test, such as `tags(network, slow)`")
+                );
+            }
+        }
+        parse_regular_groups(input)?
+    } else {
+        if is_bench {
+            if !input.peek(Paren) {
+                return Err(input.error("expected the bencher of the `bench` block"));
+            }
+            input.parse::<TokenTree>()?;
+        } else if KINDS_WITH_INPUTS.contains(&kind.as_str()) {
+            if !input.peek(Paren) {
+                return Err(
+                    input.error(format!("expected the inputs of the `{} test` block", kind))
+                );
+            }
+            input.parse::<TokenTree>()?;
+        }
+        if kind == "shuttle" && peek_ident(input, "iterations") {
+            input.parse::<Ident>()?;
+            input.parse::<LitInt>()?;
+        }
+        if input.peek(Paren) {
+            let group = input.parse::<TokenTree>()?;
+            let fixtures = fixtures(&group)
+                .ok_or_else(|| Error::new(group.span(), "expected fixture names"))?;
+            Some(Fixtures {
+                names: Vec::new(),
+                ..fixtures
+            })
+        } else {
+            None
+        }
+    };
+
+    if kind == "test" && input.peek(Token![=]) {
+        input.parse::<Token![=]>()?;
+        input.parse::<Expr>()?;
+        input.parse::<Token![;]>()?;
+    } else {
+        let body;
+        braced!(body in input);
+        if kind == "scenario" {
+            parse_phases(&body)?;
+        } else {
+            body.parse::<TokenStream>()?;
+        }
+    }
+    tokens.extend(tokens_between(after_name, input.cursor()));
+
+    Ok(Some(Block::Test {
+        name,
+        function,
+        fixtures,
+        after,
+    }))
+}
+
+/// Names the function generated for a test, e.g. `billing_refunds__refunds_once` for
+/// `refunds_once` in the flattened mod `refunds`, with the `billing_` prefix.
+fn function_name(scope: &Scope, name: &Ident) -> syn::Result<Ident> {
+    if scope.prefix.is_none() && scope.mods.is_empty() {
+        return Ok(name.clone());
+    }
+    let mut function = scope
+        .prefix
+        .map_or_else(String::new, |prefix| prefix.unraw().to_string());
+    for module in &scope.mods {
+        function.push_str(&format!("{}__", module.unraw()));
+    }
+    function.push_str(&name.unraw().to_string());
+
+    match syn::parse_str::<Ident>(&function) {
+        Ok(_) => Ok(Ident::new(&function, name.span())),
+        Err(_) => Err(Error::new(
+            name.span(),
+            format!(
+                "test `{}` generates a function named `{}`, which is not a valid identifier",
+                name, function
+            ),
+        )),
+    }
+}
+
+/// Checks that the body of a scenario is made of `given`, `when` and `then` blocks, each
+/// optional, in this order.
+fn parse_phases(input: ParseStream) -> syn::Result<()> {
+    const PHASES: [&str; 3] = ["given", "when", "then"];
+
+    let mut last = None;
+    while !input.is_empty() {
+        let phase = input.call(Ident::parse_any)?;
+        let Some(position) = PHASES.iter().position(|expected| phase == expected) else {
+            return Err(Error::new(
+                phase.span(),
+                "expected a `given`, `when` or `then` block",
+            ));
+        };
+        match last {
+            Some(last) if last == position => {
+                return Err(Error::new(
+                    phase.span(),
+                    format!("duplicate `{}` block", phase),
+                ));
+            }
+            Some(last) if last > position => {
+                return Err(Error::new(
+                    phase.span(),
+                    format!(
+                        "the `{}` block must come before the `{}` block",
+                        phase, PHASES[last]
+                    ),
+                ));
+            }
+            _ => last = Some(position),
+        }
+        let content;
+        braced!(content in input);
+        content.parse::<TokenStream>()?;
+    }
+    Ok(())
+}
+
+/// Parses the parenthesized groups and return type of a regular test: the fixture names
+/// and/or the parameters of the generated function.
+/// Parses the bound of a `within` test or the threshold of `- warn_slow`, an integer with a unit
+/// as suffix, e.g. `50ms`.
+fn parse_duration(input: ParseStream) -> syn::Result<()> {
+    let duration = input.parse::<LitInt>()?;
+    match duration.suffix() {
+        "ns" | "us" | "ms" | "s" => Ok(()),
+        _ => Err(Error::new(
+            duration.span(),
+            "expected a duration with a unit among `ns`, `us`, `ms` and `s`, e.g. `50ms`",
+        )),
+    }
+}
+
+fn parse_regular_groups(input: ParseStream) -> syn::Result<Option<Fixtures>> {
+    let mut groups = Vec::new();
+    while input.peek(Paren) {
+        groups.push(input.parse::<TokenTree>()?);
+    }
+    if input.peek(Token![->]) {
+        input.parse::<Token![->]>()?;
+        input.parse::<Type>()?;
+    }
+
+    match groups.as_slice() {
+        [] => Ok(None),
+        [group] => Ok(fixtures(group)),
+        [first, _] => fixtures(first).map(Some).ok_or_else(|| {
+            Error::new(
+                first.span(),
+                "expected fixture names before the parameters of the test",
+            )
+        }),
+        [_, _, extra, ..] => Err(Error::new(
+            extra.span(),
+            "a test takes at most a list of fixture names and a list of parameters",
+        )),
+    }
+}
+
+/// Reads a parenthesized group as a list of fixture names such as `(conn, mut buffer)`,
+/// with an optional trailing comma.
+fn fixtures(group: &TokenTree) -> Option<Fixtures> {
+    let TokenTree::Group(group) = group else {
+        return None;
+    };
+    let mut names = Vec::new();
+    let mut binding = Vec::new();
+    for token in group.stream() {
+        match token {
+            TokenTree::Ident(ident) => binding.push(ident.to_string()),
+            TokenTree::Punct(punct) if punct.as_char() == ',' && !binding.is_empty() => {
+                names.push(binding.join(" "));
+                binding.clear();
+            }
+            _ => return None,
+        }
+    }
+    if !binding.is_empty() {
+        names.push(binding.join(" "));
+    }
+    if names.is_empty() {
+        return None;
+    }
+    Some(Fixtures {
+        span: group.span(),
+        bound: names.len(),
+        names,
+    })
+}
+
+impl Fixtures {
+    /// Counts the bindings of shared resources and once fixtures among `scoped`.
+    fn scoped(&self, scoped: &[String]) -> usize {
+        self.names
+            .iter()
+            .filter(|name| scoped.contains(name))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand, expand_module, expand_suite_tests};
+    use quote::quote;
+    use syn::parse_quote;
+
+    #[test]
+    fn forwards_the_suite_to_the_declarative_backend() {
+        let expansion = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+            - teardown: teardown
+
+            test should_return_true(nbr, my_string) {
+                assert_eq!(nbr, 43);
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+                - setup: setup(i32, String)
+                - teardown: teardown
+
+                test should_return_true(nbr, my_string) {
+                    assert_eq!(nbr, 43);
+                }
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn names_unnamed_suites_tests() {
+        let expansion = expand(quote! {
+            - setup: setup(i32)
+
+            test is_short(nbr) {}
+            test_suite! {
+                test is_nested {}
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: tests] [setup:] [teardown:] []
+                - setup: setup(i32)
+
+                test is_short(nbr) {}
+                test_suite! {
+                    - name: tests
+
+                    test is_nested {}
+                }
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn nests_suites_named_by_a_path() {
+        let expansion = expand(quote! {
+            - name: integration::billing::refunds
+
+            test refunds_once {}
+        })
+        .unwrap();
+
+        let expected = quote! {
+            mod integration {
+                #[allow(unused_imports)]
+                use super::*;
+
+                mod billing {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    ::test_suite_rs::__test_suite_header! {
+                        [name: refunds] [setup:] [teardown:] []
+
+                        test refunds_once {}
+                    }
+                }
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reports_arity_mismatches() {
+        let error = expand(quote! {
+            - name: test_mod
+            - setup: fixtures::setup(i32, String)
+
+            mod nested {
+                test binds_three(a, b, c) {}
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "test `binds_three` binds 3 fixture values but setup `fixtures::setup` provides 2"
+        );
+    }
+
+    #[test]
+    fn reports_fixtures_without_setup() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            loom test binds_one(conn) {}
+        })
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("test `binds_one` binds fixture values but its suite has no setup"));
+    }
+
+    #[test]
+    fn inherits_the_setup_of_the_enclosing_suite() {
+        let result = expand(quote! {
+            - name: outer
+            - setup: setup(i32, String)
+
+            test_suite! {
+                - name: inner
+
+                test binds_two(a, b) {}
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn allows_unused_mut_on_mutable_bindings_only() {
+        let expansion = expand_module(parse_quote! {
+            mod suite {
+                #[setup]
+                fn setup() -> (i32, String) {
+                    (43, "my_string".to_owned())
+                }
+
+                #[suite_test]
+                fn reads(nbr: i32, string: String) {}
+
+                #[suite_test]
+                fn may_write(mut nbr: i32, string: String) {}
+            }
+        })
+        .unwrap()
+        .to_string();
+
+        let allow_mut = quote!(#[allow(unused_variables, unused_mut)]).to_string();
+        let allow = quote!(#[allow(unused_variables)]).to_string();
+        assert_eq!(expansion.matches(&allow_mut).count(), 1);
+        assert_eq!(expansion.matches(&allow).count(), 1);
+    }
+
+    #[test]
+    fn reports_attribute_arity_mismatches() {
+        let error = expand_module(parse_quote! {
+            mod suite {
+                #[setup]
+                fn setup() -> (i32, String) {
+                    (43, "my_string".to_owned())
+                }
+
+                #[suite_test]
+                fn binds_one(nbr: i32) {}
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "test `binds_one` binds 1 fixture values but setup `setup` provides 2"
+        );
+    }
+
+    #[test]
+    fn accepts_trailing_commas() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String,)
+
+            test binds_two(a, b,) {}
+            loom test binds_two_in_a_model(a, mut b,) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_single_expression_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+
+            test is_short(nbr, _string) = assert_eq!(nbr, 43);
+            test is_short_too = assert!(true);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_async_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+            - target: wasm
+
+            async test is_short(nbr, _string) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_miri_options() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+            - miri: ignore
+
+            miri_skip test is_short(nbr, _string) {}
+            miri_skip test is_short_too = assert!(true);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_harness_option() {
+        let result = expand(quote! {
+            - name: test_mod
+            - harness: mimic
+
+            test is_short = assert!(true);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_report_options() {
+        let result = expand(quote! {
+            - name: test_mod
+            - report: json("target/timings/{suite}.json")
+
+            test is_short = assert!(true);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_report_formats() {
+        let error = expand(quote! {
+            - name: test_mod
+            - report: xunit("target/{suite}.xml")
+
+            test is_short = assert!(true);
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown report format `xunit`, expected `junit` or `json`"
+        );
+    }
+
+    #[test]
+    fn accepts_custom_frameworks() {
+        let result = expand(quote! {
+            - name: test_mod
+            - framework: custom(crate::Descriptor::new)
+
+            test is_short = assert!(true);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_frameworks() {
+        let error = expand(quote! {
+            - name: test_mod
+            - framework: criterion(crate::Descriptor::new)
+
+            test is_short = assert!(true);
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown framework `criterion`, expected `custom`"
+        );
+    }
+
+    #[test]
+    fn accepts_bench_blocks() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(Vec<u8>)
+            - benches
+
+            bench encodes_1mb(b: &mut Bencher) (data) {
+                b.iter(|| encode(&data))
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_iai_benches() {
+        let result = expand(quote! {
+            - name: test_mod
+            - benches: iai
+
+            bench encodes_1mb(b: &mut Bencher) {
+                b.iter(|| encode(&DATA))
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_benchmark_harnesses() {
+        let error = expand(quote! {
+            - name: test_mod
+            - benches: divan
+
+            bench encodes_1mb(b: &mut Bencher) {
+                b.iter(|| encode(&DATA))
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown benchmark harness `divan`, expected `iai`"
+        );
+    }
+
+    #[test]
+    fn accepts_builtin_fixtures() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: builtin alloc_counter(AllocCounter)
+
+            test allocates_nothing(counter) = assert_eq!(counter.delta(), 0);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_teardowns_of_builtin_fixtures() {
+        let error = expand(quote! {
+            - name: test_mod
+            - setup: builtin alloc_counter(AllocCounter)
+            - teardown: teardown
+
+            test allocates_nothing(counter) = assert_eq!(counter.delta(), 0);
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "the teardown of the suite is already set"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_builtin_fixtures() {
+        let error = expand(quote! {
+            - name: test_mod
+            - setup: builtin clock(MockClock)
+
+            test is_short = assert!(true);
+        })
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "unknown builtin fixture `clock`");
+    }
+
+    #[test]
+    fn accepts_nightly_bench_blocks() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(Vec<u8>)
+
+            bench test hashes_fast(bencher: &mut Bencher) (b) {
+                bencher.iter(|| hash(&b))
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_bench_arity_mismatches() {
+        let error = expand(quote! {
+            - name: test_mod
+            - setup: setup(Vec<u8>)
+            - benches
+
+            bench encodes_1mb(b: &mut Bencher) (data, size) {
+                b.iter(|| encode(&data))
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "test `encodes_1mb` binds 2 fixture values but setup `setup` provides 1"
+        );
+    }
+
+    #[test]
+    fn forwards_compile_fail_tests() {
+        let expansion = expand(quote! {
+            - name: test_mod
+
+            compile_fail test rejects_unnamed_suites = "tests/ui/unnamed_suites.rs";
+            test is_short = assert!(true);
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+
+                compile_fail test rejects_unnamed_suites = "tests/ui/unnamed_suites.rs";
+                test is_short = assert!(true);
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn rewrites_describe_and_it_blocks() {
+        let expansion = expand(quote! {
+            - name: test_mod
+
+            describe "User registration" {
+                it "rejects duplicate e-mails" {}
+                it "3 retries, then fails!" = assert!(true);
+                it "type" {}
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+
+                mod user_registration {
+                    test rejects_duplicate_e_mails {}
+                    test _3_retries_then_fails = assert!(true);
+                    test type_ {}
+                }
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reports_misordered_scenario_phases() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            scenario checkout {
+                given {}
+                then {}
+                when {}
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "the `when` block must come before the `then` block"
+        );
+    }
+
+    #[test]
+    fn prefixes_test_names() {
+        let expansion = expand(quote! {
+            - name: test_mod
+            - prefix: billing_
+
+            test charges {}
+            mod refunds {
+                loom test refunds_once {}
+            }
+            fn helper() {}
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+
+                test billing_charges {}
+                mod refunds {
+                    loom test billing_refunds_once {}
+                }
+                fn helper() {}
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reports_prefixes_producing_invalid_names() {
+        let error = expand(quote! {
+            - name: test_mod
+            - prefix: ty
+
+            test pe {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "test `pe` generates a function named `type`, which is not a valid identifier"
+        );
+    }
+
+    #[test]
+    fn flattens_mods_into_test_names() {
+        let expansion = expand(quote! {
+            - name: test_mod
+            - flatten_names
+
+            mod users {
+                #[ignore]
+                mod admins {
+                    test lists_users {}
+                    fn helper() {}
+                }
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+
+                #[ignore]
+                #[allow(non_snake_case)]
+                test users__admins__lists_users {}
+                #[ignore]
+                fn helper() {}
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reports_collisions_of_flattened_names() {
+        let error = expand(quote! {
+            - name: test_mod
+            - flatten_names
+
+            test users__lists {}
+            mod users {
+                test lists {}
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "two tests are named `users__lists` once the mods of the suite are flattened"
+        );
+    }
+
+    #[test]
+    fn lists_the_tests_of_a_suite() {
+        let expansion = expand_suite_tests(quote! {
+            - name: test_mod
+            - prefix: billing_
+
+            test charges {}
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [suite_tests: test_mod] [setup:] [teardown:] []
+
+                test billing_charges {}
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn accepts_environment_variables() {
+        let result = expand(quote! {
+            - name: test_mod
+            - env: { "APP_MODE" => "test", "NO_COLOR" => "1", }
+
+            test reads_the_mode {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_logger_initialization() {
+        for option in [
+            quote!(-init_logger),
+            quote!(- init_logger: debug),
+            quote!(- init_logger: "my_crate=trace"),
+        ] {
+            let result = expand(quote! {
+                - name: test_mod
+                #option
+
+                test logs {}
+            });
+
+            assert!(result.is_ok());
+        }
+    }
+
+    #[test]
+    fn accepts_serial_keys() {
+        let result = expand(quote! {
+            - name: test_mod
+            - serial: database
+
+            test queries {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_serial_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - serial
+
+            test writes_the_lockfile {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_serial_and_parallel_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+            - prefix: lockfile_
+            - serial
+
+            serial test writes {}
+
+            parallel test reads = assert!(true);
+        });
+
+        let expanded = result.unwrap().to_string();
+        assert!(expanded.contains("serial test lockfile_writes"));
+        assert!(expanded.contains("parallel test lockfile_reads"));
+    }
+
+    #[test]
+    fn accepts_the_log_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - log
+
+            test logs {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_leak_check_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(Connection)
+            - leak_check
+
+            test queries(conn) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_bounded_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(Input)
+
+            test parses_fast within 50ms (input) {}
+            serial test parses_slowly within 2s (input) = parse(&input);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_bounds_without_units() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            test parses_fast within 50 {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "expected a duration with a unit among `ns`, `us`, `ms` and `s`, e.g. `50ms`"
+        );
+    }
+
+    #[test]
+    fn accepts_stack_sizes() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(Input)
+
+            test parses_deeply stack 16MB (input) {}
+            serial test parses_fast within 50ms stack 512KB tags(parser) (input) = parse(&input);
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_stack_sizes_without_units() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            test parses_deeply stack 16 {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "expected a stack size with a unit among `KB`, `MB` and `GB`, e.g. `16MB`"
+        );
+    }
+
+    #[test]
+    fn renames_the_dependencies_of_flattened_tests() {
+        let expansion = expand(quote! {
+            - name: test_mod
+            - flatten_names
+
+            mod users {
+                test creates_user {}
+                serial test logs_in after creates_user within 1s {}
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            ::test_suite_rs::__test_suite_header! {
+                [name: test_mod] [setup:] [teardown:] []
+
+                #[allow(non_snake_case)]
+                test users__creates_user {}
+                #[allow(non_snake_case)]
+                serial test users__logs_in after users__creates_user within 1s {}
+            }
+        };
+        assert_eq!(expansion.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reports_unknown_dependencies() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            test creates_user {}
+            mod sessions {
+                test logs_in after creates_user {}
+            }
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "test `logs_in` runs after `creates_user`, which isn't a test of its mod"
+        );
+    }
+
+    #[test]
+    fn reports_dependency_cycles() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            test creates_user after deletes_user {}
+            test logs_in after creates_user {}
+            test deletes_user after logs_in {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "test `creates_user` depends on itself through the tests it runs after: \
+             creates_user -> deletes_user -> logs_in -> creates_user"
+        );
+    }
+
+    #[test]
+    fn leaves_shared_resources_out_of_the_fixture_values() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+            - shared: device: Device = open_device()
+            - shared: probe: Vec<u32> = { Vec::new() }
+
+            test binds_both(nbr, device, my_string) {}
+            test binds_resources_only(probe, device) {}
+
+            test_suite! {
+                - name: nested
+
+                scenario binds_inherited_resources(device, mut nbr, my_string) {}
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_shared_resources_of_nested_suites() {
+        let error = expand(quote! {
+            - name: outer
+            - shared: device: Device = open_device()
+
+            test_suite! {
+                - name: inner
+                - shared: probe: Probe = open_probe()
+
+                test binds_probe(probe) {}
+            }
+        })
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("shared `probe` must be declared by the outermost suite"));
+    }
+
+    #[test]
+    fn reports_fixture_values_bound_among_shared_resources() {
+        let error = expand(quote! {
+            - name: test_mod
+            - shared: device: Device = open_device()
+
+            test binds_one(device, conn) {}
+        })
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("test `binds_one` binds fixture values but its suite has no setup"));
+    }
+
+    #[test]
+    fn leaves_once_fixtures_out_of_the_fixture_values() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32)
+            - setup_once: crate::warm_cache(cache: Cache, pool: Pool)
+            - shared: device: Device = open_device()
+
+            test evicts_old_entries(mut cache, nbr) {}
+            test reads_entries(cache, pool, mut device) {}
+
+            mod nested {
+                test binds_inherited_fixtures(mut pool) {}
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_once_setups_of_nested_suites() {
+        let error = expand(quote! {
+            - name: outer
+
+            test_suite! {
+                - name: inner
+                - setup_once: warm_cache(cache: Cache)
+
+                test binds_cache(cache) {}
+            }
+        })
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("`- setup_once` must be declared by the outermost suite"));
+    }
+
+    #[test]
+    fn accepts_tagged_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            test syncs_remote tags(network, slow) {}
+            serial test logs_in after syncs_remote within 1s tags(db,) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_empty_tag_lists() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            test syncs_remote tags() {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unexpected end of input, expected the tags of the test, such as `tags(network, slow)`"
+        );
+    }
+
+    #[test]
+    fn accepts_focused_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            focus test reproduces_bug {}
+            #[should_panic]
+            focus serial test panics {}
+            focus prop test parses(input in ".*") {}
+            test is_ignored {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_focused_mods() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            focus mod users {
+                test logs_in {}
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "expected a test after `focus`, such as `focus test reproduces_bug {}`"
+        );
+    }
+
+    #[test]
+    fn accepts_skipped_tests() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            skip("waiting on fixture rework") test migrates_v2 {}
+            mod users {
+                skip("flaky") test logs_in tags(network) {}
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_skipped_tests_without_a_reason() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            skip test migrates_v2 {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "expected the reason the test is skipped, such as `skip(\"waiting on fixture rework\") test`"
+        );
+    }
+
+    #[test]
+    fn accepts_tests_ignored_on_ci() {
+        let result = expand(quote! {
+            - name: test_mod
+
+            ignore_on_ci test opens_devtools {}
+            ignore_on(env = "HEADLESS") test opens_a_window tags(gui) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_tests_ignored_on_several_variables() {
+        let error = expand(quote! {
+            - name: test_mod
+
+            ignore_on(env = "HEADLESS", env = "CI") test opens_a_window {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "expected a test ignored on a single variable, such as `ignore_on(env = \"HEADLESS\") test`"
+        );
+    }
+
+    #[test]
+    fn accepts_teardown_variants() {
+        let result = expand(quote! {
+            - name: test_mod
+            - teardown(unix): kill_pgroup
+            - teardown(target_os = "windows"): jobs::close_job
+            - teardown(other): noop
+
+            test spawns_server {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_duplicate_teardown_variants() {
+        let error = expand(quote! {
+            - name: test_mod
+            - teardown(unix): kill_pgroup
+            - teardown(unix): kill_session
+
+            test spawns_server {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "the `unix` teardown of the suite is already set"
+        );
+    }
+
+    #[test]
+    fn reports_teardowns_mixed_with_variants() {
+        let error = expand(quote! {
+            - name: test_mod
+            - teardown(unix): kill_pgroup
+            - teardown: teardown
+
+            test spawns_server {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "the teardown of the suite is already set"
+        );
+    }
+
+    #[test]
+    fn accepts_the_capture_setup_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: start_database(Database)
+            - capture_setup
+
+            test queries(db) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_slow_test_warnings() {
+        let result = expand(quote! {
+            - name: test_mod
+            - warn_slow: 500ms
+
+            test parses {}
+            quiet test parses_slowly {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_setup_budgets() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup_budget: 2s
+
+            test parses {}
+
+            test_suite! {
+                - name: nested
+                - setup_budget: warn 500ms
+
+                test parses_slowly {}
+            }
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_setup_budget_modes() {
+        let error = expand(quote! {
+            - name: test_mod
+            - setup_budget: soft 2s
+
+            test parses {}
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown setup budget mode `soft`, expected `warn`"
+        );
+    }
+
+    #[test]
+    fn accepts_teardown_timeouts() {
+        let result = expand(quote! {
+            - name: test_mod
+            - teardown: teardown
+            - teardown_timeout: 10s
+
+            test parses {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_summary_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - summary
+
+            test parses {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_timings_flag() {
+        let result = expand(quote! {
+            - name: test_mod
+            - timings
+
+            test parses {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_ordered_suites() {
+        let result = expand(quote! {
+            - name: test_mod
+            - ordered
+
+            test creates_the_user {}
+            test logs_in {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn accepts_the_shuffle_audit() {
+        let result = expand(quote! {
+            - name: test_mod
+            - audit: shuffle
+
+            test parses {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_audit_modes() {
+        let result = expand(quote! {
+            - name: test_mod
+            - audit: random
+
+            test parses {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown audit mode `random`, expected `shuffle`"
+        );
+    }
+
+    #[test]
+    fn accepts_the_nextest_mode() {
+        let result = expand(quote! {
+            - name: test_mod
+            - serial
+            - nextest: per_process_setup
+
+            test parses {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_nextest_modes() {
+        let result = expand(quote! {
+            - name: test_mod
+            - nextest: shared
+
+            test parses {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown nextest mode `shared`, expected `per_process_setup`"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_tracing_modes() {
+        let result = expand(quote! {
+            - name: test_mod
+            - tracing: print
+
+            test traces {}
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown tracing mode `print`, expected `capture`"
+        );
+    }
+
+    #[test]
+    fn accepts_tokio_runtimes() {
+        let result = expand(quote! {
+            - name: test_mod
+            - setup: setup(i32, String)
+            - runtime: tokio
+            - tokio_time: paused
+
+            async test sleeps(nbr, _string) {}
+            async test sleeps_in_real_time realtime (nbr, _string) {}
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_options() {
+        let error = expand(quote! {
+            - name: test_mod
+            - set_up: setup
+        })
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "unknown suite option `set_up`");
+    }
+}
@@ -0,0 +1,44 @@
+//! Procedural macros of test_suite_rs.
+//!
+//! They hold the procedural front-end of the `test_suite!` macro. Enable it through the
+//! `proc-macro` feature of test_suite_rs rather than depending on this crate.
+
+mod front_end;
+
+use proc_macro2::TokenStream;
+use syn::{Error, ItemMod};
+
+/// Creates a test suite, see the documentation of test_suite_rs for its syntax.
+#[proc_macro]
+pub fn test_suite(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    front_end::expand(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Lists the names of the tests of a suite, see the documentation of test_suite_rs.
+#[proc_macro]
+pub fn suite_tests(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    front_end::expand_suite_tests(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Turns an ordinary inline module into a test suite: `#[setup]` and `#[teardown]` mark its
+/// fixture functions and every `#[suite_test]` function becomes a test running between them,
+/// its parameters being bound by position to the values returned by the setup.
+#[proc_macro_attribute]
+pub fn suite(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = TokenStream::from(args);
+    if let Some(arg) = args.into_iter().next() {
+        return Error::new(arg.span(), "`#[test_suite]` takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+    front_end::expand_module(syn::parse_macro_input!(input as ItemMod))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,51 @@
+//! Runs suites whose generated code is excluded from coverage reports, which requires a nightly
+//! toolchain: `cargo +nightly test --features coverage-attr --test coverage`.
+
+#![feature(coverage_attribute)]
+
+use std::cell::Cell;
+use test_suite_rs::test_suite;
+
+thread_local! {
+    static TEARDOWNS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn setup() -> (i32, String) {
+    (42, String::from("forty-two"))
+}
+
+fn teardown() {
+    TEARDOWNS.set(TEARDOWNS.get() + 1);
+}
+
+test_suite! {
+    - name: coverage_suite
+    - setup: crate::setup(i32, String)
+    - teardown: crate::teardown
+
+    use std::num::ParseIntError;
+
+    test runs_the_body(number, text) {
+        assert_eq!(number, 42);
+        assert_eq!(text, "forty-two");
+    }
+
+    test returns_results(number, _text) -> Result<(), ParseIntError> {
+        assert_eq!("42".parse::<i32>()?, number);
+        Ok(())
+    }
+
+    serial test keeps_the_test_lock(number, _text) {
+        assert_eq!(number, 42);
+    }
+
+    mod parsing {
+        test runs_the_tests_of_mods(_number, text) = assert_eq!(text.len(), 9);
+    }
+
+    test_suite! {
+        - name: nested
+
+        test inherits_the_setup(number, _text) = assert_eq!(number, 42);
+    }
+}
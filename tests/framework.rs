@@ -0,0 +1,102 @@
+//! Runs a `- framework` suite with a custom test runner, which requires a nightly toolchain:
+//! `cargo +nightly test --features nightly --test framework`.
+
+#![feature(custom_test_frameworks)]
+#![test_runner(run_tests)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use test_suite_rs::test_suite;
+
+static TEARDOWNS: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Descriptor {
+    suite: &'static str,
+    module: &'static str,
+    name: &'static str,
+    run: fn(),
+}
+
+impl Descriptor {
+    pub const fn new(
+        suite: &'static str,
+        module: &'static str,
+        name: &'static str,
+        run: fn(),
+    ) -> Self {
+        Descriptor {
+            suite,
+            module,
+            name,
+            run,
+        }
+    }
+}
+
+fn run_tests(tests: &[&Descriptor]) {
+    let mut names: Vec<_> = tests
+        .iter()
+        .map(|test| (test.suite, test.module, test.name))
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            (
+                "framework_suite",
+                "framework::framework_suite",
+                "runs_after_the_setup"
+            ),
+            (
+                "framework_suite",
+                "framework::framework_suite",
+                "runs_the_phases"
+            ),
+            (
+                "framework_suite",
+                "framework::framework_suite::users",
+                "reaches_the_tests_of_mods"
+            ),
+            (
+                "nested",
+                "framework::framework_suite::nested",
+                "is_named_after_its_suite"
+            ),
+        ]
+    );
+    for test in tests {
+        (test.run)();
+    }
+    assert_eq!(TEARDOWNS.load(Ordering::SeqCst), tests.len());
+}
+
+fn setup() -> i32 {
+    43
+}
+
+fn teardown() {
+    TEARDOWNS.fetch_add(1, Ordering::SeqCst);
+}
+
+test_suite! {
+    - name: framework_suite
+    - setup: setup(i32)
+    - teardown: teardown
+    - framework: custom(crate::Descriptor::new)
+
+    test runs_after_the_setup(nbr) = assert_eq!(nbr, 43);
+
+    scenario runs_the_phases(nbr) {
+        given { let doubled = nbr * 2; }
+        then { assert_eq!(doubled, 86); }
+    }
+
+    mod users {
+        test reaches_the_tests_of_mods(nbr) = assert_eq!(nbr, 43);
+    }
+
+    test_suite! {
+        - name: nested
+
+        test is_named_after_its_suite(nbr) = assert_eq!(nbr, 43);
+    }
+}
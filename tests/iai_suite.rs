@@ -0,0 +1,69 @@
+// A `- benches: iai` suite, whose tests run with `cargo test` and whose benchmarks run with
+// iai-callgrind from `benches/iai_suite.rs`, which includes this file.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use test_suite_rs::test_suite;
+
+static SETUPS: AtomicUsize = AtomicUsize::new(0);
+static TEARDOWNS: AtomicUsize = AtomicUsize::new(0);
+static ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn setup() -> Vec<u8> {
+    (0..=255).collect()
+}
+
+fn counted_setup() -> Vec<u8> {
+    SETUPS.fetch_add(1, Ordering::SeqCst);
+    setup()
+}
+
+fn counted_teardown() {
+    TEARDOWNS.fetch_add(1, Ordering::SeqCst);
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().map(|&byte| u32::from(byte)).sum()
+}
+
+test_suite! {
+    - name: iai_suite
+    - setup: setup(Vec<u8>)
+    - inherit_scope
+    - benches: iai
+
+    test checksums_the_data(data) = assert_eq!(checksum(&data), 32640);
+
+    bench checksums_256_bytes(b: &mut Bencher) (data) {
+        b.iter(|| checksum(&data));
+    }
+
+    mod sums {
+        bench sums_without_fixtures(b: &mut Bencher) {
+            b.iter(|| (0..256u32).sum::<u32>());
+        }
+    }
+
+    test_suite! {
+        - name: counted
+        - setup: counted_setup(Vec<u8>)
+        - teardown: counted_teardown
+        - inherit_scope
+
+        bench runs_the_setup_in_the_benchmark(b: &mut Bencher) (data) {
+            b.iter(|| {
+                ITERATIONS.fetch_add(1, Ordering::SeqCst);
+                checksum(&data)
+            });
+        }
+    }
+}
+
+#[test]
+fn runs_the_setup_and_the_routine_once_per_call() {
+    iai_suite::counted::runs_the_setup_in_the_benchmark::wrapper();
+
+    assert_eq!(SETUPS.load(Ordering::SeqCst), 1);
+    assert_eq!(TEARDOWNS.load(Ordering::SeqCst), 1);
+    assert_eq!(ITERATIONS.load(Ordering::SeqCst), 1);
+}
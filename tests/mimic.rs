@@ -0,0 +1,55 @@
+//! A `harness = false` test target running `- harness: mimic` suites with libtest-mimic.
+
+use test_suite_rs::{run_suites, test_suite};
+
+fn setup() -> i32 {
+    43
+}
+
+test_suite! {
+    - name: mimic_suite
+    - setup: setup(i32)
+    - harness: mimic
+
+    test runs_after_the_setup(nbr) = assert_eq!(nbr, 43);
+
+    test returns_the_result_of_the_test(nbr) -> Result<(), String> {
+        if nbr == 43 { Ok(()) } else { Err(format!("unexpected {nbr}")) }
+    }
+
+    scenario runs_the_phases(nbr) {
+        given { let doubled = nbr * 2; }
+        then { assert_eq!(doubled, 86); }
+    }
+
+    #[ignore = "ignored tests don't run without --ignored"]
+    test keeps_ignored_tests(_nbr) {
+        panic!("ran an ignored test");
+    }
+
+    test names_the_trials_after_the_tests(_nbr) {
+        let names: Vec<_> = trials().iter().map(|trial| trial.name().to_owned()).collect();
+        assert_eq!(names, [
+            "mimic_suite::runs_after_the_setup",
+            "mimic_suite::returns_the_result_of_the_test",
+            "mimic_suite::runs_the_phases",
+            "mimic_suite::keeps_ignored_tests",
+            "mimic_suite::names_the_trials_after_the_tests",
+            "mimic_suite::users::admins::reaches_the_tests_of_nested_mods",
+        ]);
+    }
+
+    mod users {
+        mod admins {
+            test reaches_the_tests_of_nested_mods(nbr) = assert_eq!(nbr, 43);
+        }
+    }
+
+    test_suite! {
+        - name: nested
+
+        test has_trials_of_its_own(nbr) = assert_eq!(nbr, 43);
+    }
+}
+
+run_suites!(main, mimic_suite, mimic_suite::nested);
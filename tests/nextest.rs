@@ -0,0 +1,48 @@
+//! A `harness = false` test target running suites with `NEXTEST=1`, as cargo-nextest does in the
+//! process of each test, although all of them run in this one.
+
+use test_suite_rs::{run_suites, test_suite};
+
+fn setup() -> i32 {
+    43
+}
+
+test_suite! {
+    - name: nextest_suite
+    - setup: setup(i32)
+    - harness: mimic
+    - serial
+
+    test doesnt_lock_the_suite(_nbr) = assert!(SUITE_LOCK.try_lock().is_ok());
+
+    test doesnt_acknowledge_the_model(_nbr) {
+        const { assert!(!NEXTEST_PER_PROCESS) };
+    }
+
+    test_suite! {
+        - name: per_process
+        - nextest: per_process_setup
+
+        serial test doesnt_lock_serial_tests(_nbr) = assert!(SUITE_LOCK.try_lock().is_ok());
+
+        test acknowledges_the_model(nbr) {
+            assert_eq!(nbr, 43);
+            const { assert!(NEXTEST_PER_PROCESS) };
+        }
+
+        test_suite! {
+            - name: nested
+
+            test inherits_the_acknowledgement(_nbr) {
+                const { assert!(NEXTEST_PER_PROCESS) };
+            }
+        }
+    }
+}
+
+run_suites!(run, nextest_suite, nextest_suite::per_process, nextest_suite::per_process::nested);
+
+fn main() {
+    std::env::set_var("NEXTEST", "1");
+    run();
+}
@@ -0,0 +1,38 @@
+//! Runs the `bench test` blocks of a suite as `#[bench]` functions, which requires a nightly
+//! toolchain: `cargo +nightly test --features nightly-bench --test nightly_bench`, or
+//! `cargo +nightly bench` to measure them.
+
+#![feature(test)]
+
+use std::cell::Cell;
+use test_suite_rs::test_suite;
+
+thread_local! {
+    static SETUPS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn setup() -> Vec<u8> {
+    SETUPS.set(SETUPS.get() + 1);
+    (0..=255).collect()
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().map(|&byte| u32::from(byte)).sum()
+}
+
+test_suite! {
+    - name: nightly_bench_suite
+    - setup: crate::setup(Vec<u8>)
+    - inherit_scope
+
+    bench test checksums_256_bytes(b: &mut Bencher) (data) {
+        assert_eq!(checksum(&data), 32640);
+        b.iter(|| checksum(std::hint::black_box(&data)));
+    }
+
+    bench test runs_the_setup_before_iterating(bencher: &mut Bencher) (b) {
+        let setups = SETUPS.get();
+        bencher.iter(|| checksum(&b));
+        assert_eq!(SETUPS.get(), setups);
+    }
+}
@@ -0,0 +1,44 @@
+//! Suites mixing rstest fixtures and cases into their tests, which requires the `rstest`
+//! feature: `cargo test --features rstest --test rstest`.
+
+use test_suite_rs::test_suite;
+
+fn setup() -> (i32, &'static str) {
+    (43, "my_string")
+}
+
+fn teardown() {}
+
+test_suite! {
+    - name: test_suite_rstest
+    - setup: crate::setup(i32, &'static str)
+    - teardown: crate::teardown
+
+    use rstest::*;
+
+    #[rstest]
+    #[case(1, 44)]
+    #[case(2, 45)]
+    test passes_cases_after_the_fixture(nbr, _string) (#[case] offset: i32, #[case] expected: i32) {
+        assert_eq!(nbr + offset, expected);
+    }
+
+    #[rstest::rstest]
+    test accepts_values_without_fixture(#[values(1, 2, 3)] value: u8) {
+        assert!(value > 0);
+    }
+}
+
+#[cfg(feature = "test-log")]
+test_suite! {
+    - name: test_suite_rstest_log
+    - log
+
+    #[rstest::rstest]
+    #[case(1)]
+    #[case(2)]
+    test wraps_rstest_cases(#[case] value: u8) {
+        assert!(log::max_level() > log::LevelFilter::Off);
+        assert!(value > 0);
+    }
+}
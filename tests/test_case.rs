@@ -0,0 +1,40 @@
+//! Suites keeping the `#[test_case(...)]` annotations of their tests, which requires the
+//! `test-case` feature: `cargo test --features test-case --test test_case`.
+
+use test_suite_rs::test_suite;
+
+fn setup() -> (i32, &'static str) {
+    (43, "my_string")
+}
+
+fn teardown() {}
+
+test_suite! {
+    - name: test_suite_test_case
+    - setup: crate::setup(i32, &'static str)
+    - teardown: crate::teardown
+
+    #[test_case::test_case(1, 2 => 46 ; "adds both cases")]
+    #[test_case::test_case(-43, 0 => 0)]
+    test passes_cases_after_the_fixture(nbr, _string) (a: i32, b: i32) -> i32 {
+        nbr + a + b
+    }
+
+    #[test_case::test_case("my_string")]
+    test accepts_cases_without_return_value(_nbr, string) (expected: &str) {
+        assert_eq!(string, expected);
+    }
+}
+
+#[cfg(feature = "test-log")]
+test_suite! {
+    - name: test_suite_test_case_log
+    - log
+
+    #[test_case::test_case(1)]
+    #[test_case::test_case(2)]
+    test wraps_test_case_cases(value: u8) {
+        assert!(log::max_level() > log::LevelFilter::Off);
+        assert!(value > 0);
+    }
+}
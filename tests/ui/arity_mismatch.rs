@@ -0,0 +1,14 @@
+use test_suite_rs::test_suite;
+
+fn setup() -> (u8, String) {
+    (42, String::new())
+}
+
+test_suite! {
+    - name: arity_mismatch
+    - setup: setup(u8, String)
+
+    test binds_one(_nbr) {}
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use test_suite_rs::test_suite;
+
+test_suite! {
+    test is_short {}
+}
+
+test_suite! {
+    test is_long {}
+}
+
+fn main() {}